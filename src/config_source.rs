@@ -0,0 +1,184 @@
+//! Support for loading peer friendly-name/label overlays from several sources (local files and
+//! HTTP(S) URLs) and merging them into one `PeerEntryHashMap`. A single exporter instance often
+//! needs to annotate peers whose metadata lives on several machines, so sources are merged in
+//! order with later ones winning, the same precedence `peer_entry_hashmap_try_from` already
+//! gives later `[Peer]` blocks within one config.
+use crate::wireguard_config::{peer_entry_hashmap_try_from, PeerEntryHashMap};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Bounds how long a single HTTP(S) source gets before it's treated as failed, so one
+/// unreachable or black-holed URL stalls the scrape by a fixed amount instead of hanging it
+/// indefinitely.
+const SOURCE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub(crate) enum ConfigSource {
+    File(PathBuf),
+    Url(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::File(path) => write!(f, "{}", path.display()),
+            ConfigSource::Url(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ConfigSourceError {
+    pub source: ConfigSource,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to load config source '{}': {}", self.source, self.message)
+    }
+}
+
+impl std::error::Error for ConfigSourceError {}
+
+fn load_source_text(source: &ConfigSource) -> Result<String, ConfigSourceError> {
+    match source {
+        ConfigSource::File(path) => fs::read_to_string(path).map_err(|e| ConfigSourceError {
+            source: source.clone(),
+            message: e.to_string(),
+        }),
+        ConfigSource::Url(url) => reqwest::blocking::Client::builder()
+            .timeout(SOURCE_FETCH_TIMEOUT)
+            .build()
+            .and_then(|client| client.get(url).send())
+            .and_then(|response| response.text())
+            .map_err(|e| ConfigSourceError {
+                source: source.clone(),
+                message: e.to_string(),
+            }),
+    }
+}
+
+/// Loads and merges `[Peer]` overlays from every source in order. A source that fails to load
+/// or parse (an unreachable URL, a missing file, a malformed config) is recorded in the returned
+/// `Vec<ConfigSourceError>` rather than aborting the whole scrape. Later sources override
+/// friendly names/labels set by earlier ones for the same public key.
+///
+/// `texts` is an out-parameter: it owns the fetched config text for the lifetime of the returned
+/// `PeerEntryHashMap`, whose `PeerEntry` values borrow from it. It's cleared on entry, so a
+/// caller reusing the same `Vec` across scrapes can't end up with stale text misattributed to
+/// the wrong source.
+pub(crate) fn peer_entry_hashmap_try_from_sources<'a>(
+    sources: &[ConfigSource],
+    texts: &'a mut Vec<String>,
+) -> (PeerEntryHashMap<'a>, Vec<ConfigSourceError>) {
+    texts.clear();
+    let mut merged = PeerEntryHashMap::new();
+    let mut errors = Vec::new();
+    let mut loaded_sources = Vec::new();
+
+    for source in sources {
+        match load_source_text(source) {
+            Ok(text) => {
+                texts.push(text);
+                loaded_sources.push(source);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    for (source, text) in loaded_sources.into_iter().zip(texts.iter()) {
+        match peer_entry_hashmap_try_from(text) {
+            Ok(overlay) => merged.extend(overlay),
+            Err(e) => errors.push(ConfigSourceError {
+                source: source.clone(),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    (merged, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    struct TempConfigFile {
+        path: PathBuf,
+    }
+
+    impl TempConfigFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("prometheus_wireguard_exporter_test_{}", name));
+            let mut file = fs::File::create(&path).expect("failed to create temp config file");
+            file.write_all(contents.as_bytes()).expect("failed to write temp config file");
+            TempConfigFile { path }
+        }
+    }
+
+    impl Drop for TempConfigFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    const PEER_A_V1: &str = "[Peer]\n# friendly_name=from first source\nPublicKey = 2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=\nAllowedIPs = 10.70.0.2/32\n";
+    const PEER_A_V2: &str = "[Peer]\n# friendly_name=from second source\nPublicKey = 2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=\nAllowedIPs = 10.70.0.2/32\n";
+    const PEER_B: &str = "[Peer]\n# friendly_name=laptop\nPublicKey = qnoxQoQI8KKMupLnSSureORV0wMmH7JryZNsmGVISzU=\nAllowedIPs = 10.70.0.3/32\n";
+
+    #[test]
+    fn test_later_source_overrides_earlier() {
+        let first = TempConfigFile::new("override_first", PEER_A_V1);
+        let second = TempConfigFile::new("override_second", PEER_A_V2);
+
+        let sources = vec![
+            ConfigSource::File(first.path.clone()),
+            ConfigSource::File(second.path.clone()),
+        ];
+        let mut texts = Vec::new();
+        let (merged, errors) = peer_entry_hashmap_try_from_sources(&sources, &mut texts);
+
+        assert!(errors.is_empty());
+        let entry = merged
+            .get("2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=")
+            .expect("this should have been Some!");
+        assert_eq!(Some("from second source"), entry.name());
+    }
+
+    #[test]
+    fn test_unreachable_source_does_not_abort_others() {
+        let good = TempConfigFile::new("partial_failure_good", PEER_B);
+        let missing = PathBuf::from("/nonexistent/prometheus_wireguard_exporter_test_missing.conf");
+
+        let sources = vec![
+            ConfigSource::File(missing),
+            ConfigSource::File(good.path.clone()),
+        ];
+        let mut texts = Vec::new();
+        let (merged, errors) = peer_entry_hashmap_try_from_sources(&sources, &mut texts);
+
+        assert_eq!(1, errors.len());
+        let entry = merged
+            .get("qnoxQoQI8KKMupLnSSureORV0wMmH7JryZNsmGVISzU=")
+            .expect("the reachable source should still have been merged in");
+        assert_eq!(Some("laptop"), entry.name());
+    }
+
+    #[test]
+    fn test_texts_out_param_is_cleared_on_entry() {
+        let source = TempConfigFile::new("clear_on_entry", PEER_B);
+        let sources = vec![ConfigSource::File(source.path.clone())];
+
+        let mut texts = vec!["stale text from a previous scrape".to_string()];
+        let (merged, errors) = peer_entry_hashmap_try_from_sources(&sources, &mut texts);
+
+        assert!(errors.is_empty());
+        assert!(merged.contains_key("qnoxQoQI8KKMupLnSSureORV0wMmH7JryZNsmGVISzU="));
+        drop(merged);
+        assert_eq!(1, texts.len());
+    }
+}
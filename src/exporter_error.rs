@@ -0,0 +1,30 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PeerEntryParseError {
+    PublicKeyNotFound { lines: Vec<String> },
+    AllowedIPsEntryNotFound { lines: Vec<String> },
+    InvalidPublicKey { line: String },
+    InvalidAllowedIp { entry: String },
+}
+
+impl fmt::Display for PeerEntryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerEntryParseError::PublicKeyNotFound { lines } => {
+                write!(f, "PublicKey not found in peer block: {:?}", lines)
+            }
+            PeerEntryParseError::AllowedIPsEntryNotFound { lines } => {
+                write!(f, "AllowedIPs not found in peer block: {:?}", lines)
+            }
+            PeerEntryParseError::InvalidPublicKey { line } => {
+                write!(f, "PublicKey is not a valid base64-encoded Curve25519 key: {}", line)
+            }
+            PeerEntryParseError::InvalidAllowedIp { entry } => {
+                write!(f, "AllowedIPs entry is not a valid IPv4/IPv6 CIDR: {}", entry)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PeerEntryParseError {}
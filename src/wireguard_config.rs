@@ -6,8 +6,32 @@ use std::convert::TryFrom;
 #[derive(Debug, Default, Clone)]
 pub(crate) struct PeerEntry<'a> {
     pub public_key: &'a str,
-    pub allowed_ips: &'a str,
-    pub name: Option<&'a str>,
+    /// Every CIDR from every `AllowedIPs` line in the peer's block, in encounter order. A peer
+    /// that routes several subnets lists more than one `AllowedIPs` line, and each one may itself
+    /// be a comma-separated list of CIDRs.
+    pub allowed_ips: Vec<&'a str>,
+    /// Every recognized `# key = value` comment attached to this peer's block, keyed by `key`.
+    pub comments: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> PeerEntry<'a> {
+    /// Convenience accessor for the `friendly_name` comment key, kept around since it predates
+    /// the generic `comments` map and is still the most commonly used annotation.
+    pub fn name(&self) -> Option<&'a str> {
+        self.comments.get("friendly_name").copied()
+    }
+
+    /// Returns the subset of `comments` whose key is in `allowed_keys`, in the order given. This
+    /// is the config-layer primitive an exporter would call, per an operator-configured
+    /// allowlist, to turn arbitrary comment keys into extra metric label dimensions; wiring it up
+    /// to an actual label set is outside this module, which only owns parsing.
+    pub fn extra_labels(&self, allowed_keys: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+        allowed_keys
+            .iter()
+            .filter_map(|key| self.comments.get_key_value(key))
+            .map(|(key, value)| (*key, *value))
+            .collect()
+    }
 }
 
 #[inline]
@@ -23,6 +47,41 @@ fn after_char(s: &str, c_split: char) -> &str {
     s
 }
 
+/// Base64-decodes `public_key` and, if it decodes to exactly 32 bytes (a Curve25519 key),
+/// returns the canonical re-encoded form. This way two keys that differ only in base64 padding
+/// or surrounding whitespace still compare equal, which matters since it's used as the
+/// `PeerEntryHashMap` key that ties a config's `[Peer]` block to the same peer reported by `wg`.
+fn canonicalize_public_key(public_key: &str) -> Option<String> {
+    let decoded = base64::decode(public_key).ok()?;
+    if decoded.len() == 32 {
+        Some(base64::encode(decoded))
+    } else {
+        None
+    }
+}
+
+/// Checks that `cidr` is a syntactically well-formed IPv4 or IPv6 network (an address, a slash,
+/// and a prefix length that fits the address family).
+fn is_valid_cidr(cidr: &str) -> bool {
+    let mut parts = cidr.splitn(2, '/');
+    let address = parts.next().unwrap_or("");
+    let prefix_len = match parts.next() {
+        Some(prefix_len) => prefix_len,
+        None => return false,
+    };
+
+    let prefix_len: u8 = match prefix_len.parse() {
+        Ok(prefix_len) => prefix_len,
+        Err(_) => return false,
+    };
+
+    match address.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(_)) => prefix_len <= 32,
+        Ok(std::net::IpAddr::V6(_)) => prefix_len <= 128,
+        Err(_) => false,
+    }
+}
+
 fn from_pound_line_to_key_value(line: &str) -> Option<(&str, &str)> {
     // since the pound sign is 1 byte the below slice will work
     let line = &line[1..];
@@ -45,8 +104,8 @@ impl<'a> TryFrom<&[&'a str]> for PeerEntry<'a> {
         debug!("PeerEntry::TryFrom called with lines == {:?}", lines);
 
         let mut public_key = "";
-        let mut allowed_ips = "";
-        let mut name = None;
+        let mut allowed_ips: Vec<&str> = Vec::new();
+        let mut comments = HashMap::new();
 
         for line in lines {
             let line_lowercase = line.to_lowercase();
@@ -54,36 +113,47 @@ impl<'a> TryFrom<&[&'a str]> for PeerEntry<'a> {
             if line_lowercase.starts_with("publickey") {
                 public_key = after_char(line, '=').trim();
             } else if line_lowercase.starts_with("allowedips") {
-                allowed_ips = after_char(line, '=').trim();
+                // a single AllowedIPs line can itself be a comma-separated list of CIDRs, and a
+                // peer routing several subnets may repeat the AllowedIPs line entirely
+                for cidr in after_char(line, '=').split(',') {
+                    let cidr = cidr.trim();
+                    if cidr != "" {
+                        allowed_ips.push(cidr);
+                    }
+                }
             } else if line.trim().starts_with('#') {
                 if let Some((key, value)) = from_pound_line_to_key_value(line) {
-                    // if it's a supported key, let' map it
-                    match key {
-                        "friendly_name" => {
-                            name = Some(value);
-                        }
-                        _ => {}
-                    }
+                    // we keep every recognized key/value pair, not just friendly_name, so
+                    // operators can opt individual comment keys in as extra metric labels
+                    comments.insert(key, value);
                 }
             }
         }
 
         // Sanity checks
-        // If there are more than one PublicKey or AllowedIPs we won't catch it. But
-        // WireGuard won't be working either so we can live with this simplification.
+        // If there are more than one PublicKey we won't catch it. But WireGuard won't be
+        // working either so we can live with this simplification.
         if public_key == "" {
             // we return a owned String for ergonomics. This will allocate but it's ok since it's not supposed
             // to happen :)
             let lines_owned: Vec<String> = lines.iter().map(|line| (*line).to_string()).collect();
             Err(PeerEntryParseError::PublicKeyNotFound { lines: lines_owned })
-        } else if allowed_ips == "" {
+        } else if canonicalize_public_key(public_key).is_none() {
+            Err(PeerEntryParseError::InvalidPublicKey {
+                line: public_key.to_string(),
+            })
+        } else if allowed_ips.is_empty() {
             let lines_owned: Vec<String> = lines.iter().map(|line| (*line).to_string()).collect();
             Err(PeerEntryParseError::AllowedIPsEntryNotFound { lines: lines_owned })
+        } else if let Some(invalid_cidr) = allowed_ips.iter().find(|cidr| !is_valid_cidr(cidr)) {
+            Err(PeerEntryParseError::InvalidAllowedIp {
+                entry: invalid_cidr.to_string(),
+            })
         } else {
             let pe = PeerEntry {
                 public_key,
                 allowed_ips,
-                name, // name can be None
+                comments,
             };
             debug!("PeerEntry::TryFrom returning PeerEntryHasMap == {:?}", pe);
             Ok(pe)
@@ -91,8 +161,16 @@ impl<'a> TryFrom<&[&'a str]> for PeerEntry<'a> {
     }
 }
 
-pub(crate) type PeerEntryHashMap<'a> = HashMap<&'a str, PeerEntry<'a>>;
+/// Keyed by the canonical base64 re-encoding of each peer's public key (see
+/// `canonicalize_public_key`), not the raw string from the config, so lookups are robust
+/// regardless of the original encoding's padding or spacing.
+pub(crate) type PeerEntryHashMap<'a> = HashMap<String, PeerEntry<'a>>;
 
+/// Parses friendly-name/label overlays out of a WireGuard config's `[Peer]` blocks, keyed by
+/// canonical public key. When the `netlink` feature is enabled, the runtime facts (allowed-ips,
+/// endpoint, handshake, rx/tx bytes) instead come from `wireguard_netlink::enumerate_netlink_peers`
+/// and get merged against this overlay; this function no longer needs to be the sole source of
+/// peer identity.
 pub(crate) fn peer_entry_hashmap_try_from(
     txt: &str,
 ) -> Result<PeerEntryHashMap, PeerEntryParseError> {
@@ -132,7 +210,10 @@ pub(crate) fn peer_entry_hashmap_try_from(
 
     for block in &v_blocks {
         let p: PeerEntry = PeerEntry::try_from(&block as &[&str])?;
-        hm.insert(p.public_key, p);
+        // try_from already validated the key, so canonicalization cannot fail here
+        let canonical_key = canonicalize_public_key(p.public_key)
+            .expect("public_key was already validated by PeerEntry::try_from");
+        hm.insert(canonical_key, p);
     }
 
     debug!("peer_entry_hashmap_try_from hm == {:?}", hm);
@@ -228,6 +309,28 @@ PublicKey = 6S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=
 [Peer]
 # friendly_name=cantarch
 PublicKey = L2UoJZN7RmEKsMmqaJgKG0m1S2Zs2wd2ptAf+kb3008=
+";
+
+    const TEXT_INVALID_PK: &'static str = "
+[Peer]
+# friendly_name=not a real key
+PublicKey = not_a_valid_base64_key
+AllowedIPs = 10.70.0.2/32
+";
+
+    const TEXT_MULTI_AIP: &'static str = "
+[Peer]
+# friendly_name=router
+PublicKey = 2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=
+AllowedIPs = 10.70.0.2/32, 10.70.1.0/24
+AllowedIPs = fd00::2/128
+";
+
+    const TEXT_INVALID_AIP: &'static str = "
+[Peer]
+# friendly_name=bad subnet
+PublicKey = 2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=
+AllowedIPs = 10.70.0.2/99
 ";
 
     #[test]
@@ -267,19 +370,32 @@ PublicKey = L2UoJZN7RmEKsMmqaJgKG0m1S2Zs2wd2ptAf+kb3008=
         let a: PeerEntryHashMap = peer_entry_hashmap_try_from(TEXT).unwrap();
         let entry = a.get("2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=");
         let entry = entry.expect("this should have been Some!");
-        assert_eq!(Some("OnePlus 6T"), entry.name);
+        assert_eq!(Some("OnePlus 6T"), entry.name());
 
         let entry = a.get("lqYcojJMsIZXMUw1heAFbQHBoKjCEaeo7M1WXDh/KWc=");
         let entry = entry.expect("this should have been Some!");
-        assert_eq!(Some("frcognowin10"), entry.name);
+        assert_eq!(Some("frcognowin10"), entry.name());
 
         let entry = a.get("928vO9Lf4+Mo84cWu4k1oRyzf0AR7FTGoPKHGoTMSHk=");
         let entry = entry.expect("this should have been Some!");
-        assert_eq!(Some("OnePlus 5T"), entry.name);
+        assert_eq!(Some("OnePlus 5T"), entry.name());
 
         let entry = a.get("MdVOIPKt9K2MPj/sO2NlWQbOnFJ6L/qX80mmhQwsUlA=");
         let entry = entry.expect("this should have been Some!");
-        assert_eq!(None, entry.name);
+        assert_eq!(None, entry.name());
+    }
+
+    #[test]
+    fn test_extra_labels() {
+        let a: PeerEntryHashMap = peer_entry_hashmap_try_from(TEXT).unwrap();
+        let entry = a
+            .get("2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=")
+            .expect("this should have been Some!");
+        assert_eq!(
+            vec![("friendly_name", "OnePlus 6T")],
+            entry.extra_labels(&["friendly_name", "owner"])
+        );
+        assert_eq!(Vec::<(&str, &str)>::new(), entry.extra_labels(&["owner"]));
     }
 
     #[test]
@@ -297,4 +413,28 @@ PublicKey = L2UoJZN7RmEKsMmqaJgKG0m1S2Zs2wd2ptAf+kb3008=
     fn test_parse_no_allowed_ips() {
         let _: PeerEntryHashMap = peer_entry_hashmap_try_from(TEXT_AIP).unwrap();
     }
+
+    #[test]
+    #[should_panic(expected = "InvalidPublicKey { line: \"not_a_valid_base64_key\" }")]
+    fn test_parse_invalid_public_key() {
+        let _: PeerEntryHashMap = peer_entry_hashmap_try_from(TEXT_INVALID_PK).unwrap();
+    }
+
+    #[test]
+    fn test_parse_multiple_allowed_ips() {
+        let a: PeerEntryHashMap = peer_entry_hashmap_try_from(TEXT_MULTI_AIP).unwrap();
+        let entry = a
+            .get("2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=")
+            .expect("this should have been Some!");
+        assert_eq!(
+            vec!["10.70.0.2/32", "10.70.1.0/24", "fd00::2/128"],
+            entry.allowed_ips
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidAllowedIp { entry: \"10.70.0.2/99\" }")]
+    fn test_parse_invalid_allowed_ip() {
+        let _: PeerEntryHashMap = peer_entry_hashmap_try_from(TEXT_INVALID_AIP).unwrap();
+    }
 }
@@ -0,0 +1,177 @@
+//! Optional backend that reads live peer state straight from the kernel WireGuard netlink
+//! interface, instead of shelling out to `wg` and parsing its human-readable output. Enumerating
+//! over netlink avoids a fork/exec per scrape and is immune to changes in `wg`'s text format.
+//!
+//! This module only deals in runtime facts (public key, allowed-ips, endpoint, handshake,
+//! rx/tx bytes); it knows nothing about friendly names or other comment metadata. Those still
+//! come from `peer_entry_hashmap_try_from` and get merged on top, keyed by the same canonical
+//! public key produced by `wireguard_config::canonicalize_public_key`.
+#![cfg(feature = "netlink")]
+
+use crate::wireguard_config::PeerEntryHashMap;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::SystemTime;
+use wireguard_control::{Backend, Device, InterfaceName, PeerInfo};
+
+#[derive(Debug, Clone)]
+pub(crate) struct NetlinkPeer {
+    /// Canonical base64-encoded public key, same format as `wireguard_config::PeerEntryHashMap`'s keys.
+    pub public_key: String,
+    pub allowed_ips: Vec<String>,
+    pub endpoint: Option<SocketAddr>,
+    pub last_handshake: Option<SystemTime>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+#[derive(Debug)]
+pub(crate) enum NetlinkError {
+    InterfaceNotFound { interface: String },
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for NetlinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetlinkError::InterfaceNotFound { interface } => {
+                write!(f, "WireGuard interface '{}' not found via netlink", interface)
+            }
+            NetlinkError::Io(e) => write!(f, "netlink I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for NetlinkError {}
+
+impl From<std::io::Error> for NetlinkError {
+    fn from(e: std::io::Error) -> Self {
+        NetlinkError::Io(e)
+    }
+}
+
+/// Enumerates the live peers of `interface` directly through the kernel's WireGuard netlink
+/// interface, without spawning a `wg` subprocess.
+pub(crate) fn enumerate_netlink_peers(interface: &str) -> Result<Vec<NetlinkPeer>, NetlinkError> {
+    let interface_name: InterfaceName = interface
+        .parse()
+        .map_err(|_| NetlinkError::InterfaceNotFound {
+            interface: interface.to_string(),
+        })?;
+
+    let device = Device::get(&interface_name, Backend::default()).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            NetlinkError::InterfaceNotFound {
+                interface: interface.to_string(),
+            }
+        } else {
+            NetlinkError::Io(e)
+        }
+    })?;
+
+    Ok(device.peers.into_iter().map(netlink_peer_from_info).collect())
+}
+
+fn netlink_peer_from_info(peer: PeerInfo) -> NetlinkPeer {
+    NetlinkPeer {
+        public_key: base64::encode(peer.config.public_key.as_bytes()),
+        allowed_ips: peer
+            .config
+            .allowed_ips
+            .iter()
+            .map(|ip| format!("{}/{}", ip.address, ip.cidr))
+            .collect(),
+        endpoint: peer.config.endpoint,
+        last_handshake: peer.stats.last_handshake_time,
+        rx_bytes: peer.stats.rx_bytes,
+        tx_bytes: peer.stats.tx_bytes,
+    }
+}
+
+/// Runtime facts for a peer, as read over netlink, plus whatever friendly-name/label overlay
+/// `peer_entry_hashmap_try_from` found for the same canonical public key. `name`/`comments` are
+/// `None`/empty for a peer netlink knows about but that has no matching `[Peer]` overlay entry.
+#[derive(Debug, Clone)]
+pub(crate) struct MergedPeer {
+    pub netlink: NetlinkPeer,
+    pub name: Option<String>,
+    pub comments: HashMap<String, String>,
+}
+
+/// Merges live netlink peers against a friendly-name/label overlay, keyed by the same canonical
+/// public key both sides use. A netlink peer with no matching overlay entry is still kept, just
+/// with `name: None` and empty `comments` — wg knows about the peer whether or not an operator
+/// has annotated it.
+pub(crate) fn merge_netlink_peers_with_overlay(
+    netlink_peers: Vec<NetlinkPeer>,
+    overlay: &PeerEntryHashMap,
+) -> Vec<MergedPeer> {
+    netlink_peers
+        .into_iter()
+        .map(|netlink| {
+            let entry = overlay.get(&netlink.public_key);
+            MergedPeer {
+                name: entry.and_then(|e| e.name()).map(|n| n.to_string()),
+                comments: entry
+                    .map(|e| {
+                        e.comments
+                            .iter()
+                            .map(|(k, v)| (k.to_string(), v.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                netlink,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wireguard_config::peer_entry_hashmap_try_from;
+
+    fn sample_netlink_peer(public_key: &str) -> NetlinkPeer {
+        NetlinkPeer {
+            public_key: public_key.to_string(),
+            allowed_ips: vec!["10.70.0.2/32".to_string()],
+            endpoint: None,
+            last_handshake: None,
+            rx_bytes: 0,
+            tx_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_merge_with_matching_overlay() {
+        let overlay = peer_entry_hashmap_try_from(
+            "[Peer]\n# friendly_name=OnePlus 6T\nPublicKey = 2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=\nAllowedIPs = 10.70.0.2/32\n",
+        )
+        .unwrap();
+
+        let netlink_peers = vec![sample_netlink_peer(
+            "2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=",
+        )];
+
+        let merged = merge_netlink_peers_with_overlay(netlink_peers, &overlay);
+        assert_eq!(1, merged.len());
+        assert_eq!(Some("OnePlus 6T".to_string()), merged[0].name);
+    }
+
+    #[test]
+    fn test_merge_with_no_matching_overlay() {
+        let overlay = peer_entry_hashmap_try_from(
+            "[Peer]\n# friendly_name=OnePlus 6T\nPublicKey = 2S7mA0vEMethCNQrJpJKE81/JmhgtB+tHHLYQhgM6kk=\nAllowedIPs = 10.70.0.2/32\n",
+        )
+        .unwrap();
+
+        let netlink_peers = vec![sample_netlink_peer(
+            "qnoxQoQI8KKMupLnSSureORV0wMmH7JryZNsmGVISzU=",
+        )];
+
+        let merged = merge_netlink_peers_with_overlay(netlink_peers, &overlay);
+        assert_eq!(1, merged.len());
+        assert_eq!(None, merged[0].name);
+        assert!(merged[0].comments.is_empty());
+    }
+}